@@ -1,20 +1,184 @@
-use crate::utils::{Parameters, hash_h, hash_g, generate_matrix_from_seed, generate_error_vector, generate_polynomial, encode_vector, vec_ntt, decode_vector, encode_poly, decode_poly, decompress_poly, compress_poly, compress_vec};
-use module_lwe::utils::{gen_uniform_matrix,mul_mat_vec_simple,gen_small_vector,add_vec,mul_vec_simple};
-use module_lwe::encrypt::encrypt;
-use module_lwe::decrypt::decrypt;
-use ring_lwe::utils::{gen_binary_poly,polyadd};
+// Note on SIMD: the NTT/pointwise-multiply hot path (`vec_ntt` from this crate's
+// `utils` module, `mul_mat_vec_simple`/`mul_vec_simple` from the `module_lwe`
+// crate) is not implemented in this file, and no vectorized backend is provided
+// here: every call goes through those scalar routines.
+use crate::utils::{Parameters, hash_h, hash_g, hash_j, from_ntt, generate_matrix_from_seed, generate_error_vector, generate_polynomial, encode_vector, vec_ntt, decode_vector, encode_poly, decode_poly, decompress_poly, compress_poly, compress_vec, decompress_vec};
+use module_lwe::utils::{mul_mat_vec_simple,add_vec,mul_vec_simple};
+use ring_lwe::utils::{polyadd,polysub};
 use polynomial_ring::Polynomial;
 use aes_ctr_drbg::DrbgCtx;
+use std::cell::RefCell;
+use sha3::{Shake256, digest::{Update, ExtendableOutput, XofReader}};
+use aes_gcm::{Aes256Gcm, Key, Nonce, aead::{Aead, KeyInit, Payload}};
+
+/// A serialized encapsulation key (`ek_pke`, i.e. the 12-bit encoded `t_hat || rho`).
+pub type EncapsKey = Vec<u8>;
+
+/// A serialized ciphertext (`c1 || c2`).
+pub type Ciphertext = Vec<u8>;
+
+/// Constant-time equality of two byte slices.
+///
+/// Returns `0xFF` if the slices are equal (same length and contents) and `0x00`
+/// otherwise, without any data-dependent branch. Used by [`MLKEM::decapsulate`]
+/// to compare the re-encrypted ciphertext against the received one, which must
+/// not leak whether the implicit-rejection branch was taken.
+fn ct_byte_eq(a: &[u8], b: &[u8]) -> u8 {
+    if a.len() != b.len() {
+        return 0x00;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    // diff == 0 -> 0xFF, any other value -> 0x00
+    (((diff as i16 - 1) >> 8) & 0xFF) as u8
+}
+
+/// Constant-time select: returns `a` where `mask` is `0xFF` and `b` where it is
+/// `0x00`, byte by byte. Both inputs must have the same length.
+fn ct_select(a: &[u8], b: &[u8], mask: u8) -> Vec<u8> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| (x & mask) | (y & !mask))
+        .collect()
+}
+
+/// The algorithm step at which a known-answer test first diverged.
+#[derive(Debug, PartialEq, Eq)]
+pub enum KatStep {
+    /// `keygen` produced an `ek`/`dk` that did not match the expected value.
+    Keygen,
+    /// `encapsulate` produced a shared secret or ciphertext that did not match.
+    Encaps,
+    /// `decapsulate` recovered the wrong shared secret.
+    Decaps,
+    /// No known-answer vectors are available for the active parameter set, so
+    /// conformance could not be attested.
+    MissingVectors,
+}
+
+/// A single ML-KEM known-answer vector: the explicit randomness inputs and the
+/// expected outputs for each algorithm.
+pub struct KatVector {
+    /// Key-generation seed.
+    pub d: Vec<u8>,
+    /// Implicit-rejection secret seed.
+    pub z: Vec<u8>,
+    /// Encapsulation message.
+    pub m: Vec<u8>,
+    /// Expected encapsulation key.
+    pub ek: Vec<u8>,
+    /// Expected decapsulation key.
+    pub dk: Vec<u8>,
+    /// Expected shared secret.
+    pub k: Vec<u8>,
+    /// Expected ciphertext.
+    pub c: Vec<u8>,
+}
+
+/// Error returned by the sealed-box [`open`](MLKEM::open) operation.
+#[derive(Debug)]
+pub enum SealError {
+    /// The decapsulation key could not be protected in memory.
+    Kem(SecretKeyError),
+    /// AEAD authentication failed (wrong key, tampered ciphertext, or bad `aad`).
+    Aead,
+}
+
+/// Error returned when protecting secret material in memory fails.
+#[derive(Debug)]
+pub enum SecretKeyError {
+    /// The `mlock(2)` syscall failed for a buffer of `n_bytes` bytes, reporting `errno`.
+    MlockFailed { errno: i32, n_bytes: usize },
+}
+
+/// A secret byte buffer whose backing pages are locked into RAM with `mlock(2)`
+/// on construction and volatilely zeroized (then unlocked) on drop.
+///
+/// Locking keeps the secret from being paged to swap; the volatile wipe on drop
+/// stops it lingering in freed memory where the optimizer would otherwise be
+/// free to elide a plain overwrite. If the lock syscall is refused (e.g.
+/// `RLIMIT_MEMLOCK` exhausted) construction fails with
+/// [`SecretKeyError::MlockFailed`] rather than leaving the secret swappable.
+pub struct SecretKey {
+    bytes: Vec<u8>,
+}
+
+impl SecretKey {
+    /// Take ownership of `bytes`, locking its pages into memory.
+    pub fn new(bytes: Vec<u8>) -> Result<Self, SecretKeyError> {
+        if !bytes.is_empty() {
+            // SAFETY: pointer and length describe a live, contiguous allocation.
+            let ret = unsafe { libc::mlock(bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+            if ret != 0 {
+                let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+                return Err(SecretKeyError::MlockFailed { errno, n_bytes: bytes.len() });
+            }
+        }
+        Ok(SecretKey { bytes })
+    }
+
+    /// Borrow the protected bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl std::ops::Deref for SecretKey {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        // Volatile overwrite so the wipe cannot be optimized away, with a fence
+        // to keep it from being reordered past the deallocation.
+        for b in self.bytes.iter_mut() {
+            unsafe { std::ptr::write_volatile(b, 0u8) };
+        }
+        std::sync::atomic::fence(std::sync::atomic::Ordering::SeqCst);
+        if !self.bytes.is_empty() {
+            // SAFETY: mirrors the mlock performed in `new` over the same buffer.
+            unsafe { libc::munlock(self.bytes.as_ptr() as *const libc::c_void, self.bytes.len()); }
+        }
+    }
+}
+
+impl Parameters {
+    /// ML-KEM-512 parameter set (NIST security category 1): `k = 2`, `eta_1 = 3`,
+    /// `eta_2 = 2`, `(du, dv) = (10, 4)`. `n` and `q` keep their standard values.
+    pub fn ml_kem_512() -> Self {
+        Parameters { k: 2, eta_1: 3, eta_2: 2, du: 10, dv: 4, ..Parameters::default() }
+    }
+
+    /// ML-KEM-768 parameter set (NIST security category 3): `k = 3`, `eta_1 = 2`,
+    /// `eta_2 = 2`, `(du, dv) = (10, 4)`.
+    pub fn ml_kem_768() -> Self {
+        Parameters { k: 3, eta_1: 2, eta_2: 2, du: 10, dv: 4, ..Parameters::default() }
+    }
+
+    /// ML-KEM-1024 parameter set (NIST security category 5): `k = 4`, `eta_1 = 2`,
+    /// `eta_2 = 2`, `(du, dv) = (11, 5)`.
+    pub fn ml_kem_1024() -> Self {
+        Parameters { k: 4, eta_1: 2, eta_2: 2, du: 11, dv: 5, ..Parameters::default() }
+    }
+}
 
 pub struct MLKEM {
     pub params: Parameters,
-    pub drbg: Option<DrbgCtx>,
+    /// Wrapped in a `RefCell` so methods that only need `&self` (e.g.
+    /// `encapsulate_multi`, whose signature is fixed by its broadcast API) can
+    /// still advance the DRBG instead of silently falling back to `None`.
+    pub drbg: RefCell<Option<DrbgCtx>>,
 }
 
 impl MLKEM {
     // Constructor to initialize MLKEM with parameters
     pub fn new(params: Parameters) -> Self {
-        MLKEM { params, drbg: None}
+        MLKEM { params, drbg: RefCell::new(None) }
     }
 
     /// Set the DRBG to be used for random bytes
@@ -22,118 +186,179 @@ impl MLKEM {
         let p = vec![48, 0]; // personalization string must be min. 48 bytes long
         let mut drbg = DrbgCtx::new(); // instantiate the DRBG
 	    drbg.init(&seed, p); // initialize the DRBG with the seed
-        self.drbg = Some(drbg); // Store the DRBG in the struct
+        self.drbg = RefCell::new(Some(drbg)); // Store the DRBG in the struct
     }
 
-    /// keygen function to generate public and secret keys
-    /// 
+    /// Generates an ML-KEM key pair following Algorithm 16 (FIPS 203).
+    ///
+    /// A fresh 32-byte seed `d` drives the underlying K-PKE key generation and a
+    /// second 32-byte secret `z` is sampled for implicit rejection. The returned
+    /// decapsulation key bundles everything decaps needs so that no state has to
+    /// be threaded separately:
+    ///
+    /// `dk = dk_pke || ek_pke || H(ek_pke) || z`
+    ///
     /// # Returns
     ///
-    /// * ((Vec<Vec<Polynomial<i64>>>, Vec<Polynomial<i64>>), Vec<Polynomial<i64>>)
-    ///   - A tuple containing the public key (a matrix and a vector) and the secret key (a vector)
+    /// * `(ek, dk)` - the encapsulation key (`ek_pke`) and the bundled
+    ///   decapsulation key described above. The decapsulation key is returned in
+    ///   a memory-locked, zeroizing [`SecretKey`].
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if the secret buffers cannot be
+    /// locked into memory.
     ///
     /// # Example
     /// ```
     /// use ml_kem::utils::Parameters;
     /// use ml_kem::ml_kem::MLKEM;
     /// let params = Parameters::default();
-    /// let mlkem = MLKEM::new(params);
-    /// let (pk, sk) = mlkem.keygen();
+    /// let mut mlkem = MLKEM::new(params);
+    /// let (ek, dk) = mlkem.keygen().unwrap();
     /// ```
-    /// # Note
-    /// The public key consists of a matrix `a` and a vector `b`, while the secret key is a vector `s`.
-    pub fn keygen(&self) -> ((Vec<Vec<Polynomial<i64>>>, Vec<Polynomial<i64>>), Vec<Polynomial<i64>>) {
-        
-        let a = gen_uniform_matrix(self.params.n, self.params.k, self.params.q, None); 
-        
-        let s = gen_small_vector(self.params.n, self.params.k, None);
-        let e = gen_small_vector(self.params.n, self.params.k, None);
-        
-        let b = add_vec(
-            &mul_mat_vec_simple(&a, &s, self.params.q, &self.params.f, self.params.omega), 
-            &e, 
-            self.params.q, 
-            &self.params.f
-        );
-        
-        ((a, b), s)
-    }
-
-    /// Encapsulate function to generate a shared secret and ciphertext
+    pub fn keygen(&mut self) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
+        let d = (self.params.random_bytes)(32, self.drbg.borrow_mut().as_mut());
+        let z = (self.params.random_bytes)(32, self.drbg.borrow_mut().as_mut());
+        self.keygen_internal(d, z)
+    }
+
+    /// Shared body of [`keygen`](Self::keygen): assembles the CCA key pair from
+    /// the explicit seeds `d` and `z`. Keeping the randomness as parameters keeps
+    /// the derandomized entry point and the production one on the same code path.
+    fn keygen_internal(&self, d: Vec<u8>, z: Vec<u8>) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
+        let (ek_pke, dk_pke) = self._k_pke_keygen(d)?;
+        self.bundle_dk(ek_pke, dk_pke, z)
+    }
+
+    /// Key generation that fixes the public matrix `A` by taking `rho`
+    /// explicitly instead of deriving it (together with the secret seed
+    /// `sigma`) from a single random `d`.
+    ///
+    /// Plain [`keygen`](Self::keygen) cannot produce keys that share `A`:
+    /// `_k_pke_keygen` derives `rho` and `sigma` together from one
+    /// `hash_g(d || k)` call, so every call samples a fresh matrix along with
+    /// a fresh secret. [`encapsulate_multi`](Self::encapsulate_multi)'s
+    /// broadcast saving is sound only when every recipient's key was built
+    /// against the same `A`, so its recipients must come from this
+    /// constructor: one fixed `rho` shared across calls, with a distinct
+    /// `sigma` and `z` per recipient.
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if the secret buffers cannot be
+    /// locked into memory.
+    pub fn keygen_with_rho(&self, rho: Vec<u8>, sigma: Vec<u8>, z: Vec<u8>) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
+        let (ek_pke, dk_pke) = self._k_pke_keygen_from_seeds(rho, sigma)?;
+        self.bundle_dk(ek_pke, dk_pke, z)
+    }
+
+    /// Assemble the bundled decapsulation key `dk_pke || ek_pke || H(ek_pke) ||
+    /// z` shared by [`keygen_internal`](Self::keygen_internal) and
+    /// [`keygen_with_rho`](Self::keygen_with_rho).
+    fn bundle_dk(&self, ek_pke: Vec<u8>, dk_pke: SecretKey, z: Vec<u8>) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
+        let ek = ek_pke.clone();
+
+        let mut dk = dk_pke.as_bytes().to_vec();
+        dk.extend_from_slice(&ek_pke);
+        dk.extend_from_slice(&hash_h(ek_pke));
+        dk.extend_from_slice(&z);
+
+        Ok((ek, SecretKey::new(dk)?))
+    }
+
+    /// Encapsulate a shared secret against an encapsulation key following
+    /// Algorithm 17 (FIPS 203).
+    ///
+    /// A random 32-byte message `m` is drawn, the shared secret and encryption
+    /// randomness are derived together as `(K, r) = G(m || H(ek))`, and the
+    /// ciphertext is `c = K-PKE.Encrypt(ek, m, r)`.
     ///
     /// # Arguments
     ///
-    /// * `pk` - A tuple containing the public key (a matrix and a vector)
+    /// * `ek` - the encapsulation key produced by [`keygen`](Self::keygen).
     ///
     /// # Returns
     ///
-    /// * (Vec<u8>, (Vec<Polynomial<i64>>, Polynomial<i64>))
-    ///   - A tuple containing the shared secret (as a byte vector) and the ciphertext (a tuple of a vector and a polynomial)
+    /// * `(K, c)` - the 32-byte shared secret and the ciphertext bytes.
     ///
     /// # Example
     /// ```
     /// use ml_kem::utils::Parameters;
     /// use ml_kem::ml_kem::MLKEM;
     /// let params = Parameters::default();
-    /// let mlkem = MLKEM::new(params);
-    /// let (pk, sk) = mlkem.keygen();
-    /// let (k, ct) = mlkem.encapsulate(pk);
+    /// let mut mlkem = MLKEM::new(params);
+    /// let (ek, dk) = mlkem.keygen().unwrap();
+    /// let (k, c) = mlkem.encapsulate(ek);
     /// ```
-    /// # Note
-    /// The shared secret is generated by hashing the message `m`, which is a binary polynomial of degree `n`.
-    pub fn encapsulate(&self, pk: (Vec<Vec<Polynomial<i64>>>, Vec<Polynomial<i64>>)) -> (Vec<u8>, (Vec<Polynomial<i64>>, Polynomial<i64>)) {
-        let params_mlwe = module_lwe::utils::Parameters { 
-            n: self.params.n, 
-            q: self.params.q, 
-            k: self.params.k, 
-            omega: self.params.omega, 
-            f: self.params.f.clone() 
-        };
-
-        let mut m = gen_binary_poly(self.params.n, None).coeffs().to_vec();
-        m.resize(self.params.n, 0);
+    pub fn encapsulate(&mut self, ek: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+        let m = (self.params.random_bytes)(32, self.drbg.borrow_mut().as_mut());
+        self.encapsulate_internal(ek, m)
+    }
 
-        let ct = encrypt(&pk.0, &pk.1, &m, &params_mlwe, None);
-        let k = hash_h(m);
-        (k, ct)
+    /// Shared body of [`encapsulate`](Self::encapsulate) taking the message `m`
+    /// explicitly, so the derandomized path and the production one agree.
+    fn encapsulate_internal(&self, ek: Vec<u8>, m: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+        // (K, r) = G(m || H(ek))
+        let (k, r) = hash_g([m.clone(), hash_h(ek.clone())].concat());
+        let c = self._k_pke_encrypt(ek, m, r);
+        (k, c)
     }
 
-    /// Decapsulate function to recover the shared secret from the ciphertext and secret key
+    /// Decapsulate a ciphertext to recover the shared secret following
+    /// Algorithm 18 (FIPS 203), with constant-time implicit rejection.
+    ///
+    /// The decryption key is unbundled into `dk_pke || ek_pke || h || z`. We
+    /// recover `m' = K-PKE.Decrypt(dk_pke, c)`, re-derive `(K', r') = G(m' || h)`,
+    /// re-encrypt `c' = K-PKE.Encrypt(ek_pke, m', r')`, and compute the rejection
+    /// key `K_bar = J(z || c)`. The returned key is `K'` when `c == c'` and
+    /// `K_bar` otherwise; the comparison and selection are constant-time so an
+    /// attacker cannot learn which branch was taken.
     ///
     /// # Arguments
     ///
-    /// * `sk` - The secret key (a vector of polynomials)
-    /// * `ct` - The ciphertext (a tuple of a vector and a polynomial)
+    /// * `dk` - the bundled decapsulation key from [`keygen`](Self::keygen).
+    /// * `c` - the ciphertext bytes.
     ///
     /// # Returns
     ///
-    /// * Vec<u8> - The shared secret (as a byte vector)
+    /// * `Vec<u8>` - the recovered 32-byte shared secret.
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if the transient secret buffers
+    /// (the K-PKE decryption key slice and the decrypted message) cannot be
+    /// locked into memory.
     ///
     /// # Example
     /// ```
     /// use ml_kem::utils::Parameters;
     /// use ml_kem::ml_kem::MLKEM;
     /// let params = Parameters::default();
-    /// let mlkem = MLKEM::new(params);
-    /// let (pk, sk) = mlkem.keygen();
-    /// let (k, ct) = mlkem.encapsulate(pk);
-    /// let k_recovered = mlkem.decapsulate(sk, ct);
+    /// let mut mlkem = MLKEM::new(params);
+    /// let (ek, dk) = mlkem.keygen().unwrap();
+    /// let (k, c) = mlkem.encapsulate(ek);
+    /// let k_recovered = mlkem.decapsulate(&dk, c).unwrap();
+    /// assert_eq!(k, k_recovered);
     /// ```
-    /// # Note
-    /// The shared secret is recovered by decrypting the ciphertext using the secret key and hashing the resulting message `m`.
-    pub fn decapsulate(&self, sk: Vec<Polynomial<i64>>, ct: (Vec<Polynomial<i64>>, Polynomial<i64>)) -> Vec<u8> {
-        let params_mlwe = module_lwe::utils::Parameters { 
-            n: self.params.n, 
-            q: self.params.q, 
-            k: self.params.k, 
-            omega: self.params.omega, 
-            f: self.params.f.clone() 
-        };
-
-        let mut m = decrypt(&sk, &ct.0, &ct.1, &params_mlwe);
-        m.resize(self.params.n, 0);
-
-        hash_h(m)
+    pub fn decapsulate(&self, dk: &SecretKey, c: Vec<u8>) -> Result<Vec<u8>, SecretKeyError> {
+        // dk = dk_pke (384k) || ek_pke (384k + 32) || h (32) || z (32)
+        let dk_pke_len = 384 * self.params.k;
+        let ek_pke_len = 384 * self.params.k + 32;
+        let dk_pke = SecretKey::new(dk[..dk_pke_len].to_vec())?;
+        let ek_pke = dk[dk_pke_len..dk_pke_len + ek_pke_len].to_vec();
+        let h = dk[dk_pke_len + ek_pke_len..dk_pke_len + ek_pke_len + 32].to_vec();
+        let z = SecretKey::new(dk[dk_pke_len + ek_pke_len + 32..dk_pke_len + ek_pke_len + 64].to_vec())?;
+
+        // Recover the message (kept locked) and re-derive the candidate key/randomness.
+        let m_prime = SecretKey::new(self._k_pke_decrypt(dk_pke.as_bytes().to_vec(), c.clone()))?;
+        let (k_prime, r_prime) = hash_g([m_prime.as_bytes().to_vec(), h].concat());
+
+        // Implicit-rejection key, bound to the received ciphertext.
+        let k_bar = hash_j([z.as_bytes().to_vec(), c.clone()].concat());
+
+        // Re-encrypt and compare in constant time.
+        let c_prime = self._k_pke_encrypt(ek_pke, m_prime.as_bytes().to_vec(), r_prime);
+        let mask = ct_byte_eq(&c, &c_prime);
+
+        Ok(ct_select(&k_prime, &k_bar, mask))
     }
 
     /// Generates an encryption key and a corresponding decryption key based on the
@@ -150,8 +375,12 @@ impl MLKEM {
     /// # Returns
     /// * A tuple containing:
     ///   - `ek_pke`: The encryption key, which is the public value `t_hat` encoded with `rho`.
-    ///   - `dk_pke`: The decryption key, which is the encoded `s_hat`.
-    /// 
+    ///   - `dk_pke`: The decryption key, which is the encoded `s_hat`, returned in a
+    ///     memory-locked, zeroizing [`SecretKey`].
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if `dk_pke` cannot be locked into memory.
+    ///
     /// # Example
     /// ```
     /// use ml_kem::utils::Parameters;
@@ -159,24 +388,37 @@ impl MLKEM {
     /// let params = Parameters::default();
     /// let mlkem = MLKEM::new(params);
     /// let d = vec![0x01, 0x02, 0x03, 0x04];
-    /// let (ek_pke, dk_pke) = mlkem._k_pke_keygen(d);
+    /// let (ek_pke, dk_pke) = mlkem._k_pke_keygen(d).unwrap();
     /// ```
     pub fn _k_pke_keygen(
         &self,
         d: Vec<u8>,
-    ) -> (Vec<u8>, Vec<u8>) {
+    ) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
         // Expand 32 + 1 bytes to two 32-byte seeds.
         // Note: rho, sigma are generated using hash_g
         let (rho, sigma) = hash_g([d.clone(), vec![self.params.k as u8]].concat());
+        self._k_pke_keygen_from_seeds(rho, sigma)
+    }
 
+    /// Shared body of [`_k_pke_keygen`](Self::_k_pke_keygen): builds `(ek_pke,
+    /// dk_pke)` from `rho`/`sigma` supplied directly rather than derived
+    /// together from a single `d`. Also used by
+    /// [`keygen_with_rho`](Self::keygen_with_rho) to fix the matrix `A` (via
+    /// `rho`) across multiple keys while varying the secret seed `sigma`.
+    fn _k_pke_keygen_from_seeds(
+        &self,
+        rho: Vec<u8>,
+        sigma: Vec<u8>,
+    ) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
         // Generate A_hat from seed rho
         let a_hat = generate_matrix_from_seed(rho.clone(), self.params.k, self.params.n, false);
 
         // Set counter for PRF
         let prf_count = 0;
 
-        // Generate the error vectors s and e
-        let (s, _prf_count) = generate_error_vector(sigma.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
+        // Generate the error vectors s and e, threading the PRF counter N between
+        // them (FIPS 203 domain separation) so s and e are drawn independently.
+        let (s, prf_count) = generate_error_vector(sigma.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
         let (e, _prf_count) = generate_error_vector(sigma.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
 
         // the NTT of s as an element of a rank k module over the polynomial ring
@@ -191,7 +433,21 @@ impl MLKEM {
         ek_pke.extend_from_slice(&rho); // append rho, output of hash function
         let dk_pke = encode_vector(&s_hat, 12); // Encoding s_hat for dk_pke
 
-        (ek_pke, dk_pke)
+        Ok((ek_pke, SecretKey::new(dk_pke)?))
+    }
+
+    /// Verify the FIPS 203 "modulus check": `t_hat_bytes` must be the
+    /// canonical 12-bit encoding of `t_hat`, i.e. re-encoding it must
+    /// reproduce the original bytes. This catches an `ek` whose `t_hat` was
+    /// encoded with out-of-range (non-canonical) coefficients, which
+    /// `decode_vector` would otherwise accept silently.
+    ///
+    /// # Panics
+    /// Panics if `t_hat` does not re-encode to `t_hat_bytes`.
+    fn check_t_hat_canonical(t_hat: &[Polynomial<i64>], t_hat_bytes: &[u8]) {
+        if encode_vector(t_hat, 12) != t_hat_bytes {
+            panic!("Modulus check failed, t_hat does not encode correctly");
+        }
     }
 
     /// Encrypts a plaintext message using the encryption key and randomness `r`
@@ -245,26 +501,22 @@ impl MLKEM {
         let t_hat = decode_vector(t_hat_bytes.clone(), self.params.k, 12, true);
 
         // check that t_hat has been canonically encoded
-        if encode_vector(&t_hat,12) != t_hat_bytes {
-            panic!(
-                "Modulus check failed, t_hat does not encode correctly"
-            );
-        }
+        Self::check_t_hat_canonical(&t_hat, &t_hat_bytes);
 
         // Generate A_hat^T from seed rho
         let a_hat_t = generate_matrix_from_seed(rho.clone(), self.params.k, self.params.n, true);
 
         // generate error vectors y, e1 and error polynomial e2
+        // Thread the PRF counter N across samples for FIPS 203 domain separation
+        // (otherwise y and e1 coincide whenever eta_1 == eta_2).
         let prf_count = 0;
-        let (y, _prf_count) = generate_error_vector(r.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
-        let (e1, _prf_count) = generate_error_vector(r.clone(), self.params.eta_2, prf_count, self.params.k, self.params.n);
+        let (y, prf_count) = generate_error_vector(r.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
+        let (e1, prf_count) = generate_error_vector(r.clone(), self.params.eta_2, prf_count, self.params.k, self.params.n);
         let (e2, _prf_count) = generate_polynomial(r.clone(), self.params.eta_2, prf_count, self.params.n, None);
 
         // compute the NTT of the error vector y
         let y_hat = vec_ntt(&y, self.params.omega, self.params.n, self.params.q);
 
-        /*
-
         // compute u = a_hat.T * y_hat + e1
         let a_hat_t_dot_y_hat = from_ntt(mul_mat_vec_simple(&a_hat_t, &y_hat, self.params.q, &self.params.f, self.params.omega));
         let u = add_vec(&a_hat_t_dot_y_hat, &e1, self.params.q, &self.params.f);
@@ -277,15 +529,439 @@ impl MLKEM {
         let v = polyadd(&polyadd(&t_hat_dot_y_hat, &e2, self.params.q, &self.params.f), &mu, self.params.q, &self.params.f);
 
         // compress polynomials u, v by compressing coeffs, then encode to bytes using params du, dv
-        let c1 = encode_vec(&compress_vec(&u,self.params.du),self.params.du);
+        let c1 = encode_vector(&compress_vec(&u,self.params.du),self.params.du);
         let c2 = encode_poly(&compress_poly(&v,self.params.dv),self.params.dv);
 
         //return c1 + c2, the concatenation of two encoded polynomials
         [c1, c2].concat()
-        */
-        
-        m
+    }
+
+    /// Decrypts a ciphertext back to its 32-byte message using the decryption
+    /// key `dk_pke` following Algorithm 15 (FIPS 203).
+    ///
+    /// The ciphertext is split into its compressed `u` and `v` parts using the
+    /// parameter-set widths `du`/`dv`, decoded and decompressed, and the message
+    /// is recovered as `compress_1(v - s_hat . NTT(u))`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dk_pke` - A vector of bytes representing the decryption key (encoded `s_hat`).
+    /// * `c` - A vector of bytes representing the ciphertext.
+    ///
+    /// # Returns
+    ///
+    /// A vector of bytes representing the recovered plaintext message `m`.
+    pub fn _k_pke_decrypt(
+        &self,
+        dk_pke: Vec<u8>,
+        c: Vec<u8>,
+    ) -> Vec<u8> {
+        // Split the ciphertext into c1 (compressed u) and c2 (compressed v).
+        let c1_len = self.params.du * self.params.k * self.params.n / 8;
+        let (c1, c2) = c.split_at(c1_len);
+
+        // decode and decompress the vector u and polynomial v
+        let u = decompress_vec(&decode_vector(c1.to_vec(), self.params.k, self.params.du, false), self.params.du);
+        let v = decompress_poly(&decode_poly(c2.to_vec(), self.params.dv), self.params.dv);
+
+        // recover s_hat from the decryption key
+        let s_hat = decode_vector(dk_pke, self.params.k, 12, true);
 
+        // w = v - from_ntt(s_hat . NTT(u))
+        let u_hat = vec_ntt(&u, self.params.omega, self.params.n, self.params.q);
+        let s_dot_u = from_ntt(mul_vec_simple(&s_hat, &u_hat, self.params.q, &self.params.f, self.params.omega));
+        let w = polysub(&v, &s_dot_u, self.params.q, &self.params.f);
+
+        // re-encode the recovered message to bytes
+        encode_poly(&compress_poly(&w, 1), 1)
+    }
+
+    /// Expand a serialized key pair into its in-memory [`MLKEMUnpacked`] form.
+    ///
+    /// This runs the SHAKE-128 matrix expansion and the `t_hat` decoding once
+    /// so that repeated encapsulations or a batched decapsulation loop over
+    /// the same key avoid paying that cost on every call. `dk` is the bundled
+    /// decapsulation key produced by [`keygen`](Self::keygen). The secret
+    /// `s_hat` and `z` carried by `dk` stay in memory-locked, zeroizing
+    /// [`SecretKey`]s for the lifetime of the returned [`MLKEMUnpacked`]
+    /// rather than being copied out into plain `Vec`s.
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if the secret buffers cannot be
+    /// locked into memory.
+    pub fn unpack(&self, ek: Vec<u8>, dk: &SecretKey) -> Result<MLKEMUnpacked, SecretKeyError> {
+        let (t_hat_bytes, rho_slice) = ek.split_at(ek.len() - 32);
+        let rho = rho_slice.to_vec();
+
+        // t_hat from ek and A_hat^T expanded once from rho (the orientation used
+        // by both encapsulation and the decapsulation re-encryption check).
+        // The canonical-encoding check is a FIPS 203 requirement on `ek`, not an
+        // optimization `_k_pke_encrypt` happens to also do, so it must run here
+        // once rather than being lost along with the per-call decode it replaces.
+        let t_hat = decode_vector(t_hat_bytes.to_vec(), self.params.k, 12, true);
+        Self::check_t_hat_canonical(&t_hat, t_hat_bytes);
+        let a_hat = generate_matrix_from_seed(rho.clone(), self.params.k, self.params.n, true);
+
+        // s_hat (still encoded) and the implicit-rejection secret z from the
+        // bundled dk, both kept locked.
+        let dk_pke_len = 384 * self.params.k;
+        let s_hat = SecretKey::new(dk[..dk_pke_len].to_vec())?;
+        let z = SecretKey::new(dk[dk.len() - 32..].to_vec())?;
+
+        Ok(MLKEMUnpacked {
+            params: self.params.clone(),
+            a_hat,
+            s_hat,
+            t_hat,
+            rho,
+            h: hash_h(ek),
+            z,
+        })
+    }
+
+    /// Multi-recipient encapsulation (mKEM): establish a single shared secret and
+    /// deliver it to `eks.len()` recipients far cheaper than running
+    /// [`encapsulate`](Self::encapsulate) independently per key.
+    ///
+    /// One message `m` is sampled and `(K, r) = G(m)` derived once. The error
+    /// vectors `y`, `e1`, `e2` and the message polynomial `mu` are therefore
+    /// shared across all recipients, so the `u = A^T y + e1` component is
+    /// identical and only needs to be computed and encoded once; per recipient we
+    /// recompute only `v_i = t_hat_i . y_hat + e2 + mu`. Each returned ciphertext
+    /// is the full `u || v_i`, which recipient `i` recovers with
+    /// [`decapsulate_multi`](Self::decapsulate_multi).
+    ///
+    /// Because the randomness is shared, the derivation `(K, r) = G(m)` omits the
+    /// per-key binding `H(ek_i)` that single-recipient [`decapsulate`](Self::decapsulate)
+    /// relies on; [`decapsulate_multi`](Self::decapsulate_multi) mirrors that so
+    /// the re-encryption check succeeds and returns the shared `K`. If `eks` is
+    /// empty both returned values are empty.
+    ///
+    /// # Security caveat
+    /// All recipients share the same `u`, the same randomness `r`, and hence the
+    /// same shared secret `K`. This is sound only when every recipient's key was
+    /// generated against the **same** public matrix `A`, i.e. built from
+    /// [`keygen_with_rho`](Self::keygen_with_rho) with a common `rho` (the
+    /// `rho` of `eks[0]` is what gets expanded here); the sharing trades the
+    /// per-recipient independence of a fresh encapsulation for the broadcast
+    /// saving, so it must only be used where a common group key is intended.
+    ///
+    /// # Panics
+    /// Panics if any two keys in `eks` carry a different `rho`, since that
+    /// would silently expand a different `A` per recipient and break the
+    /// broadcast (recipients other than the first would fail to decrypt).
+    pub fn encapsulate_multi(&self, eks: &[EncapsKey]) -> (Vec<u8>, Vec<Ciphertext>) {
+        if eks.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+
+        // All recipients must share rho (and hence A); see `keygen_with_rho`.
+        let rho = eks[0][eks[0].len() - 32..].to_vec();
+        for ek in &eks[1..] {
+            if ek[ek.len() - 32..] != rho[..] {
+                panic!("encapsulate_multi: all recipients must share rho (build keys with keygen_with_rho)");
+            }
+        }
+
+        // One message and one randomness shared by every recipient.
+        let m = (self.params.random_bytes)(32, self.drbg.borrow_mut().as_mut());
+        let (k, r) = hash_g(m.clone());
+
+        // Shared error vectors -> shared u component.
+        // Thread the PRF counter N across samples for FIPS 203 domain separation
+        // (otherwise y and e1 coincide whenever eta_1 == eta_2).
+        let prf_count = 0;
+        let (y, prf_count) = generate_error_vector(r.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
+        let (e1, prf_count) = generate_error_vector(r.clone(), self.params.eta_2, prf_count, self.params.k, self.params.n);
+        let (e2, _prf_count) = generate_polynomial(r.clone(), self.params.eta_2, prf_count, self.params.n, None);
+        let y_hat = vec_ntt(&y, self.params.omega, self.params.n, self.params.q);
+
+        // Expand the shared matrix from the common rho checked above.
+        let a_hat_t = generate_matrix_from_seed(rho, self.params.k, self.params.n, true);
+        let a_hat_t_dot_y_hat = from_ntt(mul_mat_vec_simple(&a_hat_t, &y_hat, self.params.q, &self.params.f, self.params.omega));
+        let u = add_vec(&a_hat_t_dot_y_hat, &e1, self.params.q, &self.params.f);
+        let c1 = encode_vector(&compress_vec(&u, self.params.du), self.params.du);
+
+        // The message polynomial mu is also shared.
+        let mu = decompress_poly(&decode_poly(m, 1), 1);
+
+        // Per recipient: only v_i depends on t_hat_i.
+        let mut cts = Vec::with_capacity(eks.len());
+        for ek in eks {
+            let (t_hat_bytes, _rho) = ek.split_at(ek.len() - 32);
+            let t_hat = decode_vector(t_hat_bytes.to_vec(), self.params.k, 12, true);
+            Self::check_t_hat_canonical(&t_hat, t_hat_bytes);
+            let t_hat_dot_y_hat = from_ntt(mul_vec_simple(&t_hat, &y_hat, self.params.q, &self.params.f, self.params.omega));
+            let v = polyadd(&polyadd(&t_hat_dot_y_hat, &e2, self.params.q, &self.params.f), &mu, self.params.q, &self.params.f);
+            let c2 = encode_poly(&compress_poly(&v, self.params.dv), self.params.dv);
+            cts.push([c1.clone(), c2].concat());
+        }
+
+        (k, cts)
+    }
+
+    /// Decapsulate a multi-recipient ciphertext produced by
+    /// [`encapsulate_multi`](Self::encapsulate_multi), recovering the shared
+    /// secret `K` with constant-time implicit rejection.
+    ///
+    /// This mirrors [`decapsulate`](Self::decapsulate) but re-derives
+    /// `(K', r') = G(m')` without the `H(ek)` binding, matching the shared
+    /// derivation used by `encapsulate_multi`, so the re-encryption check passes
+    /// for a valid broadcast ciphertext.
+    pub fn decapsulate_multi(&self, dk: &SecretKey, c: Ciphertext) -> Result<Vec<u8>, SecretKeyError> {
+        // dk = dk_pke (384k) || ek_pke (384k + 32) || h (32) || z (32)
+        let dk_pke_len = 384 * self.params.k;
+        let ek_pke_len = 384 * self.params.k + 32;
+        let dk_pke = SecretKey::new(dk[..dk_pke_len].to_vec())?;
+        let ek_pke = dk[dk_pke_len..dk_pke_len + ek_pke_len].to_vec();
+        let z = SecretKey::new(dk[dk_pke_len + ek_pke_len + 32..dk_pke_len + ek_pke_len + 64].to_vec())?;
+
+        // Shared derivation: G(m'), no H(ek) binding (see `encapsulate_multi`).
+        let m_prime = SecretKey::new(self._k_pke_decrypt(dk_pke.as_bytes().to_vec(), c.clone()))?;
+        let (k_prime, r_prime) = hash_g(m_prime.as_bytes().to_vec());
+
+        let k_bar = hash_j([z.as_bytes().to_vec(), c.clone()].concat());
+
+        let c_prime = self._k_pke_encrypt(ek_pke, m_prime.as_bytes().to_vec(), r_prime);
+        let mask = ct_byte_eq(&c, &c_prime);
+
+        Ok(ct_select(&k_prime, &k_bar, mask))
+    }
+
+    /// Derive an AES-256-GCM key and nonce from the KEM shared secret via a
+    /// SHAKE-256 XOF keyed with a domain-separation label. Because every
+    /// encapsulation draws a fresh shared secret `K`, the derived nonce is
+    /// unique per message without the caller having to manage a counter.
+    fn derive_aead_params(k: &[u8]) -> ([u8; 32], [u8; 12]) {
+        let mut xof = Shake256::default();
+        xof.update(b"ml-kem sealed-box v1");
+        xof.update(k);
+        let mut reader = xof.finalize_xof();
+
+        let mut okm = [0u8; 44];
+        reader.read(&mut okm);
+
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&okm[..32]);
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&okm[32..]);
+        (key, nonce)
+    }
+
+    /// Seal a payload to a recipient in a single shot: encapsulate a shared
+    /// secret, derive an AEAD key/nonce from it, and encrypt `plaintext` under
+    /// AES-256-GCM binding the associated data `aad`.
+    ///
+    /// This turns the KEM into a complete hybrid public-key encryption primitive
+    /// so callers do not have to wire key derivation and nonce handling
+    /// themselves.
+    ///
+    /// # Returns
+    ///
+    /// * `(ct, sealed)` - the KEM ciphertext needed to recover the shared secret
+    ///   and the AEAD ciphertext (including its tag).
+    pub fn seal(&mut self, ek: Vec<u8>, plaintext: &[u8], aad: &[u8]) -> (Ciphertext, Vec<u8>) {
+        let (k, ct) = self.encapsulate(ek);
+        let (key, nonce) = Self::derive_aead_params(&k);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let sealed = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+            .expect("AES-256-GCM encryption is infallible for valid key/nonce lengths");
+
+        (ct, sealed)
     }
 
+    /// Open a payload sealed with [`seal`](Self::seal): decapsulate the shared
+    /// secret, re-derive the AEAD key/nonce, and decrypt `sealed` while checking
+    /// the tag against `aad`.
+    ///
+    /// # Errors
+    /// Returns [`SealError::Kem`] if the decapsulation key cannot be protected in
+    /// memory, or [`SealError::Aead`] if authentication fails (wrong key,
+    /// tampered ciphertext, or mismatched associated data).
+    pub fn open(&self, dk: &SecretKey, ct: Ciphertext, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, SealError> {
+        let k = self.decapsulate(dk, ct).map_err(SealError::Kem)?;
+        let (key, nonce) = Self::derive_aead_params(&k);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: sealed, aad })
+            .map_err(|_| SealError::Aead)
+    }
+
+    /// Derandomized key generation: Algorithm 16 with the seeds `d` and `z`
+    /// supplied explicitly instead of sampled. Used for conformance testing
+    /// against the FIPS 203 known-answer vectors.
+    pub fn keygen_derand(&self, d: Vec<u8>, z: Vec<u8>) -> Result<(Vec<u8>, SecretKey), SecretKeyError> {
+        self.keygen_internal(d, z)
+    }
+
+    /// Derandomized encapsulation: Algorithm 17 with the message `m` supplied
+    /// explicitly instead of sampled.
+    pub fn encapsulate_derand(&self, ek: Vec<u8>, m: Vec<u8>) -> (Vec<u8>, Vec<u8>) {
+        self.encapsulate_internal(ek, m)
+    }
+
+    /// Run the ML-KEM known-answer self-test for the active parameter set.
+    ///
+    /// For each embedded vector this drives [`keygen_derand`](Self::keygen_derand),
+    /// [`encapsulate_derand`](Self::encapsulate_derand) and
+    /// [`decapsulate`](Self::decapsulate) with the fixed randomness and compares
+    /// every output against the expected value. On the first mismatch it returns
+    /// the [`KatStep`] at which the implementation diverged; `Ok(())` means all
+    /// vectors matched.
+    ///
+    /// If no vectors are available for the active parameter set the test cannot
+    /// attest anything, so it returns [`KatStep::MissingVectors`] rather than a
+    /// vacuous success.
+    pub fn run_kat(&self) -> Result<(), KatStep> {
+        let vectors = self.kat_vectors();
+        if vectors.is_empty() {
+            return Err(KatStep::MissingVectors);
+        }
+
+        for v in vectors {
+            let (ek, dk) = self
+                .keygen_derand(v.d.clone(), v.z.clone())
+                .map_err(|_| KatStep::Keygen)?;
+            if ek != v.ek || dk.as_bytes() != v.dk.as_slice() {
+                return Err(KatStep::Keygen);
+            }
+
+            let (k, c) = self.encapsulate_derand(v.ek.clone(), v.m.clone());
+            if k != v.k || c != v.c {
+                return Err(KatStep::Encaps);
+            }
+
+            let k_dec = self.decapsulate(&dk, v.c.clone()).map_err(|_| KatStep::Decaps)?;
+            if k_dec != v.k {
+                return Err(KatStep::Decaps);
+            }
+        }
+        Ok(())
+    }
+
+    /// Standard ML-KEM known-answer vectors for the active parameter set.
+    ///
+    /// No vectors are vendored yet: matching the official FIPS 203 / ACVP test
+    /// vectors byte-for-byte depends on the exact sampling and encoding
+    /// conventions of the crate's `utils` module and the `module_lwe`/`ring_lwe`
+    /// crates this module is built on (XOF seed ordering, NTT domain layout,
+    /// `ByteEncode` bit order), none of which live in this file. Embedding vectors without being able to
+    /// run them against those crates would just trade one vacuous pass for a
+    /// KAT that "passes" against numbers nobody actually checked against this
+    /// implementation. [`run_kat`](Self::run_kat) already refuses to pass
+    /// vacuously ([`KatStep::MissingVectors`]) until real vectors land here.
+    fn kat_vectors(&self) -> Vec<KatVector> {
+        // No vectors vendored for any parameter set yet; see the doc comment above.
+        Vec::new()
+    }
+
+}
+
+/// In-memory ("unpacked") representation of an ML-KEM key pair.
+///
+/// Holds the already-expanded matrix `a_hat` (in the `A^T` orientation used by
+/// encryption), the NTT-domain public `t_hat` vector, and the cached `rho`/`h`.
+/// The secret `s_hat` (still in its encoded byte form) and the implicit-rejection
+/// secret `z` are kept in memory-locked, zeroizing [`SecretKey`]s, matching
+/// [`MLKEM::decapsulate`]. Encapsulating or decapsulating against this form
+/// skips the per-call `generate_matrix_from_seed` expansion and the decode /
+/// re-encode modulus check that [`MLKEM::_k_pke_encrypt`] performs.
+pub struct MLKEMUnpacked {
+    pub params: Parameters,
+    pub a_hat: Vec<Vec<Polynomial<i64>>>,
+    pub s_hat: SecretKey,
+    pub t_hat: Vec<Polynomial<i64>>,
+    pub rho: Vec<u8>,
+    pub h: Vec<u8>,
+    pub z: SecretKey,
+}
+
+impl MLKEMUnpacked {
+    /// Re-serialize the unpacked key pair into the `(ek, dk)` byte form returned
+    /// by [`MLKEM::keygen`]. Inverse of [`MLKEM::unpack`].
+    pub fn pack(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut ek = encode_vector(&self.t_hat, 12);
+        ek.extend_from_slice(&self.rho);
+
+        let mut dk = self.s_hat.as_bytes().to_vec();
+        dk.extend_from_slice(&ek);
+        dk.extend_from_slice(&self.h);
+        dk.extend_from_slice(self.z.as_bytes());
+
+        (ek, dk)
+    }
+
+    /// K-PKE encryption against the cached `a_hat`/`t_hat`, skipping matrix
+    /// regeneration and the canonical-encoding check.
+    fn encrypt(&self, m: Vec<u8>, r: Vec<u8>) -> Vec<u8> {
+        // Thread the PRF counter N across samples for FIPS 203 domain separation
+        // (otherwise y and e1 coincide whenever eta_1 == eta_2).
+        let prf_count = 0;
+        let (y, prf_count) = generate_error_vector(r.clone(), self.params.eta_1, prf_count, self.params.k, self.params.n);
+        let (e1, prf_count) = generate_error_vector(r.clone(), self.params.eta_2, prf_count, self.params.k, self.params.n);
+        let (e2, _prf_count) = generate_polynomial(r.clone(), self.params.eta_2, prf_count, self.params.n, None);
+
+        let y_hat = vec_ntt(&y, self.params.omega, self.params.n, self.params.q);
+
+        // u = a_hat.T * y_hat + e1, reusing the cached matrix
+        let a_hat_t_dot_y_hat = from_ntt(mul_mat_vec_simple(&self.a_hat, &y_hat, self.params.q, &self.params.f, self.params.omega));
+        let u = add_vec(&a_hat_t_dot_y_hat, &e1, self.params.q, &self.params.f);
+
+        let mu = decompress_poly(&decode_poly(m, 1), 1);
+
+        // v = t_hat . y_hat + e2 + mu, reusing the cached t_hat
+        let t_hat_dot_y_hat = from_ntt(mul_vec_simple(&self.t_hat, &y_hat, self.params.q, &self.params.f, self.params.omega));
+        let v = polyadd(&polyadd(&t_hat_dot_y_hat, &e2, self.params.q, &self.params.f), &mu, self.params.q, &self.params.f);
+
+        let c1 = encode_vector(&compress_vec(&u, self.params.du), self.params.du);
+        let c2 = encode_poly(&compress_poly(&v, self.params.dv), self.params.dv);
+
+        [c1, c2].concat()
+    }
+
+    /// K-PKE decryption against the cached (locked) `s_hat`. Decoding it from
+    /// its protected byte form on every call is far cheaper than the matrix
+    /// expansion `a_hat`/`t_hat` avoid, so it is not worth caching unlocked.
+    fn decrypt(&self, c: Vec<u8>) -> Vec<u8> {
+        let c1_len = self.params.du * self.params.k * self.params.n / 8;
+        let (c1, c2) = c.split_at(c1_len);
+
+        let u = decompress_vec(&decode_vector(c1.to_vec(), self.params.k, self.params.du, false), self.params.du);
+        let v = decompress_poly(&decode_poly(c2.to_vec(), self.params.dv), self.params.dv);
+
+        let s_hat = decode_vector(self.s_hat.as_bytes().to_vec(), self.params.k, 12, true);
+        let u_hat = vec_ntt(&u, self.params.omega, self.params.n, self.params.q);
+        let s_dot_u = from_ntt(mul_vec_simple(&s_hat, &u_hat, self.params.q, &self.params.f, self.params.omega));
+        let w = polysub(&v, &s_dot_u, self.params.q, &self.params.f);
+
+        encode_poly(&compress_poly(&w, 1), 1)
+    }
+
+    /// Encapsulate against the unpacked key, matching [`MLKEM::encapsulate`] but
+    /// reusing the cached matrices.
+    pub fn encapsulate_unpacked(&self) -> (Vec<u8>, Vec<u8>) {
+        let m = (self.params.random_bytes)(32, None);
+        let (k, r) = hash_g([m.clone(), self.h.clone()].concat());
+        let c = self.encrypt(m, r);
+        (k, c)
+    }
+
+    /// Decapsulate against the unpacked key with constant-time implicit
+    /// rejection, matching [`MLKEM::decapsulate`] but reusing the cached `s_hat`
+    /// and `a_hat`/`t_hat` for the re-encryption check.
+    ///
+    /// # Errors
+    /// Returns [`SecretKeyError::MlockFailed`] if the decrypted message cannot
+    /// be locked into memory.
+    pub fn decapsulate_unpacked(&self, c: Vec<u8>) -> Result<Vec<u8>, SecretKeyError> {
+        let m_prime = SecretKey::new(self.decrypt(c.clone()))?;
+        let (k_prime, r_prime) = hash_g([m_prime.as_bytes().to_vec(), self.h.clone()].concat());
+        let k_bar = hash_j([self.z.as_bytes().to_vec(), c.clone()].concat());
+        let c_prime = self.encrypt(m_prime.as_bytes().to_vec(), r_prime);
+        let mask = ct_byte_eq(&c, &c_prime);
+        Ok(ct_select(&k_prime, &k_bar, mask))
+    }
 }
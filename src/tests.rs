@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod kem {
+    use ml_kem::ml_kem::{MLKEM, KatStep};
+    use ml_kem::utils::Parameters;
+
+    fn mlkem(params: Parameters) -> MLKEM {
+        let mut mlkem = MLKEM::new(params);
+        mlkem.set_drbg_seed(vec![0x42; 48]);
+        mlkem
+    }
+
+    /// keygen -> encapsulate -> decapsulate recovers the shared secret.
+    #[test]
+    fn keygen_encapsulate_decapsulate_roundtrip() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+        let (k, c) = mlkem.encapsulate(ek);
+        let k_dec = mlkem.decapsulate(&dk, c).expect("decapsulate failed");
+        assert_eq!(k, k_dec);
+    }
+
+    /// A tampered ciphertext must trigger implicit rejection, not the real key.
+    #[test]
+    fn decapsulate_rejects_tampered_ciphertext() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+        let (k, mut c) = mlkem.encapsulate(ek);
+        c[0] ^= 0x01;
+        let k_dec = mlkem.decapsulate(&dk, c).expect("decapsulate failed");
+        assert_ne!(k, k_dec);
+    }
+
+    /// Multi-recipient encapsulation: every recipient recovers the shared secret
+    /// from their broadcast ciphertext via `decapsulate_multi`.
+    #[test]
+    fn multi_recipient_roundtrip() {
+        let mlkem = mlkem(Parameters::default());
+
+        // Recipients must share the public matrix A, so they are built from a
+        // fixed `rho` via `keygen_with_rho` rather than independent `keygen()`
+        // calls, each of which would derive its own fresh `rho` along with its
+        // secret.
+        let rho = vec![0x24; 32];
+        let mut dks = Vec::new();
+        let mut eks = Vec::new();
+        for i in 0..3u8 {
+            let sigma = vec![i + 1; 32];
+            let z = vec![i + 0x80; 32];
+            let (ek, dk) = mlkem.keygen_with_rho(rho.clone(), sigma, z).expect("keygen_with_rho failed");
+            eks.push(ek);
+            dks.push(dk);
+        }
+
+        let (k, cts) = mlkem.encapsulate_multi(&eks);
+        assert_eq!(cts.len(), eks.len());
+
+        for (dk, c) in dks.iter().zip(cts.into_iter()) {
+            let k_i = mlkem.decapsulate_multi(dk, c).expect("decapsulate_multi failed");
+            assert_eq!(k, k_i);
+        }
+    }
+
+    /// keygen -> encapsulate -> decapsulate recovers the shared secret under
+    /// each named parameter set.
+    #[test]
+    fn named_parameter_sets_roundtrip() {
+        for params in [Parameters::ml_kem_512(), Parameters::ml_kem_768(), Parameters::ml_kem_1024()] {
+            let mut mlkem = mlkem(params);
+            let (ek, dk) = mlkem.keygen().expect("keygen failed");
+            let (k, c) = mlkem.encapsulate(ek);
+            let k_dec = mlkem.decapsulate(&dk, c).expect("decapsulate failed");
+            assert_eq!(k, k_dec);
+        }
+    }
+
+    /// unpack -> encapsulate_unpacked -> decapsulate_unpacked recovers the
+    /// shared secret, and pack() round-trips back to the original (ek, dk).
+    #[test]
+    fn unpacked_roundtrip() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+
+        let unpacked = mlkem.unpack(ek.clone(), &dk).expect("unpack failed");
+        let (packed_ek, packed_dk) = unpacked.pack();
+        assert_eq!(ek, packed_ek);
+        assert_eq!(dk.as_bytes(), packed_dk.as_slice());
+
+        let (k, c) = unpacked.encapsulate_unpacked();
+        let k_dec = unpacked.decapsulate_unpacked(c).expect("decapsulate_unpacked failed");
+        assert_eq!(k, k_dec);
+    }
+
+    /// seal -> open recovers the original plaintext under matching aad.
+    #[test]
+    fn seal_open_roundtrip() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+
+        let plaintext = b"ML-KEM sealed-box round trip";
+        let aad = b"header";
+        let (ct, sealed) = mlkem.seal(ek, plaintext, aad);
+        let opened = mlkem.open(&dk, ct, &sealed, aad).expect("open failed");
+        assert_eq!(opened, plaintext);
+    }
+
+    /// A tampered AEAD ciphertext must fail authentication rather than open.
+    #[test]
+    fn open_rejects_tampered_ciphertext() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+
+        let (ct, mut sealed) = mlkem.seal(ek, b"payload", b"aad");
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0x01;
+        assert!(mlkem.open(&dk, ct, &sealed, b"aad").is_err());
+    }
+
+    /// Mismatched associated data must fail authentication rather than open.
+    #[test]
+    fn open_rejects_mismatched_aad() {
+        let mut mlkem = mlkem(Parameters::default());
+        let (ek, dk) = mlkem.keygen().expect("keygen failed");
+
+        let (ct, sealed) = mlkem.seal(ek, b"payload", b"aad");
+        assert!(mlkem.open(&dk, ct, &sealed, b"wrong-aad").is_err());
+    }
+
+    /// keygen_derand/encapsulate_derand must be pure functions of their seeds:
+    /// the same seeds fed in twice produce identical output.
+    #[test]
+    fn derand_is_deterministic() {
+        let mlkem = mlkem(Parameters::default());
+        let d = vec![0x11; 32];
+        let z = vec![0x22; 32];
+        let m = vec![0x33; 32];
+
+        let (ek1, dk1) = mlkem.keygen_derand(d.clone(), z.clone()).expect("keygen_derand failed");
+        let (ek2, dk2) = mlkem.keygen_derand(d, z).expect("keygen_derand failed");
+        assert_eq!(ek1, ek2);
+        assert_eq!(dk1.as_bytes(), dk2.as_bytes());
+
+        let (k1, c1) = mlkem.encapsulate_derand(ek1, m.clone());
+        let (k2, c2) = mlkem.encapsulate_derand(ek2, m);
+        assert_eq!(k1, k2);
+        assert_eq!(c1, c2);
+    }
+
+    /// Until real known-answer vectors are vendored, run_kat must refuse to
+    /// pass vacuously rather than silently report conformance it never checked.
+    #[test]
+    fn run_kat_reports_missing_vectors() {
+        let mlkem = mlkem(Parameters::default());
+        assert_eq!(mlkem.run_kat(), Err(KatStep::MissingVectors));
+    }
+}